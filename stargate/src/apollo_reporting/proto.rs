@@ -0,0 +1,110 @@
+//! Minimal subset of Apollo's usage-reporting `reports.proto` `Trace`
+//! message needed to report FTV1 data. Field *numbers* below match
+//! `reports.proto` exactly (`Trace.start_time` = 4, `Trace.duration_ns` =
+//! 11, `Trace.root` = 14, `Node.error` = 11, `Node.child` = 12); fields
+//! stargate doesn't populate (resolver timing, cache policy, client info,
+//! ...) are omitted rather than stubbed.
+
+use super::TraceNode;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Timestamp {
+    #[prost(int64, tag = "1")]
+    pub seconds: i64,
+    #[prost(int32, tag = "2")]
+    pub nanos: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Error {
+    #[prost(string, tag = "1")]
+    pub message: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Node {
+    #[prost(string, tag = "1")]
+    pub response_name: String,
+    #[prost(message, repeated, tag = "11")]
+    pub error: Vec<Error>,
+    #[prost(message, repeated, tag = "12")]
+    pub child: Vec<Node>,
+}
+
+impl From<&TraceNode> for Node {
+    fn from(node: &TraceNode) -> Self {
+        Node {
+            response_name: node.response_name.clone(),
+            error: node
+                .errors
+                .iter()
+                .map(|error| Error {
+                    message: error.message.clone(),
+                })
+                .collect(),
+            child: node.children.iter().map(Node::from).collect(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Trace {
+    #[prost(message, optional, tag = "4")]
+    pub start_time: Option<Timestamp>,
+    #[prost(uint64, tag = "11")]
+    pub duration_ns: u64,
+    #[prost(message, optional, tag = "14")]
+    pub root: Option<Node>,
+}
+
+impl From<&super::Trace> for Trace {
+    fn from(trace: &super::Trace) -> Self {
+        Trace {
+            start_time: Some(Timestamp {
+                seconds: (trace.start_time_unix_nanos / 1_000_000_000) as i64,
+                nanos: (trace.start_time_unix_nanos % 1_000_000_000) as i32,
+            }),
+            duration_ns: trace.duration_ns,
+            root: Some(Node::from(&trace.root)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_tags_match_reports_proto() {
+        // Regression test for the tag mix-up that shipped initially:
+        // encode a trace with a single error on a single child node and
+        // check the bytes contain the expected (tag, wire-type) headers
+        // rather than decoding with a second implementation we don't have.
+        let trace = super::super::Trace {
+            start_time_unix_nanos: 1_000_000_000,
+            duration_ns: 42,
+            root: TraceNode {
+                response_name: String::new(),
+                errors: Vec::new(),
+                children: vec![TraceNode {
+                    response_name: String::from("me"),
+                    errors: vec![super::super::TraceError {
+                        message: String::from("boom"),
+                    }],
+                    children: Vec::new(),
+                }],
+            },
+        };
+
+        let proto = Trace::from(&trace);
+        let mut buf = Vec::new();
+        prost::Message::encode(&proto, &mut buf).unwrap();
+
+        // field 4, wire type 2 (length-delimited message) -> tag byte (4 << 3) | 2 = 34
+        assert!(buf.contains(&34), "missing Trace.start_time (tag 4) header");
+        // field 14, wire type 2 -> (14 << 3) | 2 = 114
+        assert!(buf.contains(&114), "missing Trace.root (tag 14) header");
+        // field 11, wire type 0 (varint) for duration_ns -> (11 << 3) | 0 = 88
+        assert!(buf.contains(&88), "missing Trace.duration_ns (tag 11) header");
+    }
+}
@@ -0,0 +1,111 @@
+//! Command-line options for the tracing subsystem.
+//!
+//! Kept separate from `apollo_stargate_lib::common::Opt` (which this crate
+//! doesn't own) so every flag stargate's tracing setup reads is actually
+//! defined, parsed from argv, and testable in this tree.
+
+use std::str::FromStr;
+use structopt::StructOpt;
+
+/// Which backend to export spans to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceExporter {
+    Jaeger,
+    OtlpGrpc,
+    OtlpHttp,
+}
+
+impl FromStr for TraceExporter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jaeger" => Ok(TraceExporter::Jaeger),
+            "otlp-grpc" => Ok(TraceExporter::OtlpGrpc),
+            "otlp-http" => Ok(TraceExporter::OtlpHttp),
+            other => Err(format!(
+                "unknown trace exporter '{}' (expected jaeger, otlp-grpc, or otlp-http)",
+                other
+            )),
+        }
+    }
+}
+
+/// How to decide whether a given trace gets sampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceSampler {
+    AlwaysOn,
+    AlwaysOff,
+    Ratio,
+}
+
+impl FromStr for TraceSampler {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always_on" => Ok(TraceSampler::AlwaysOn),
+            "always_off" => Ok(TraceSampler::AlwaysOff),
+            "ratio" => Ok(TraceSampler::Ratio),
+            other => Err(format!(
+                "unknown trace sampler '{}' (expected always_on, always_off, or ratio)",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TracingOpt {
+    /// Trace exporter to send spans to.
+    #[structopt(long, default_value = "jaeger")]
+    pub trace_exporter: TraceExporter,
+
+    /// Collector endpoint for the selected trace exporter.
+    #[structopt(long, default_value = "http://localhost:14268/api/traces")]
+    pub trace_endpoint: String,
+
+    /// Trace sampling strategy.
+    #[structopt(long, default_value = "always_on")]
+    pub trace_sampler: TraceSampler,
+
+    /// Sampling ratio used when `--trace-sampler ratio` is selected.
+    #[structopt(long, default_value = "1.0")]
+    pub trace_sample_ratio: f64,
+
+    /// Apollo Studio API key. FTV1 reporting is disabled unless this and
+    /// `--apollo-graph-ref` are both set.
+    #[structopt(long, env = "APOLLO_KEY")]
+    pub apollo_key: Option<String>,
+
+    /// Apollo Studio graph ref, e.g. `my-graph@my-variant`.
+    #[structopt(long, env = "APOLLO_GRAPH_REF")]
+    pub apollo_graph_ref: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_known_trace_exporters() {
+        assert_eq!(TraceExporter::from_str("jaeger"), Ok(TraceExporter::Jaeger));
+        assert_eq!(
+            TraceExporter::from_str("otlp-grpc"),
+            Ok(TraceExporter::OtlpGrpc)
+        );
+        assert_eq!(
+            TraceExporter::from_str("otlp-http"),
+            Ok(TraceExporter::OtlpHttp)
+        );
+        assert!(TraceExporter::from_str("zipkin").is_err());
+    }
+
+    #[test]
+    fn parses_known_trace_samplers() {
+        assert_eq!(TraceSampler::from_str("always_on"), Ok(TraceSampler::AlwaysOn));
+        assert_eq!(TraceSampler::from_str("always_off"), Ok(TraceSampler::AlwaysOff));
+        assert_eq!(TraceSampler::from_str("ratio"), Ok(TraceSampler::Ratio));
+        assert!(TraceSampler::from_str("probability").is_err());
+    }
+}
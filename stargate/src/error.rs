@@ -0,0 +1,111 @@
+//! Turns a query-execution failure into a spec-compliant GraphQL error
+//! response instead of aborting the worker.
+
+use actix_web::http::StatusCode;
+use apollo_stargate_lib::QueryError;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::Span;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphQLError {
+    pub message: String,
+    // QueryError's path is a field-name path (Vec<String>); it doesn't
+    // expose list-index segments, so there's nothing to represent one as.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<Vec<String>>,
+    pub extensions: ErrorExtensions,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorExtensions {
+    pub code: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphQLErrorResponse {
+    pub data: Option<Value>,
+    pub errors: Vec<GraphQLError>,
+}
+
+/// Maps a `QueryError` to the HTTP status and GraphQL error body that should
+/// be sent back to the client, and records the failure on the current span
+/// so it shows up alongside the request in traces.
+pub fn response_for(err: QueryError) -> (StatusCode, GraphQLErrorResponse) {
+    let span = Span::current();
+    span.record("otel.status_code", &"ERROR");
+    span.record("error.message", &tracing::field::display(&err));
+
+    let (status, code, path) = status_code_and_extensions(&err);
+
+    (
+        status,
+        GraphQLErrorResponse {
+            data: None,
+            errors: vec![GraphQLError {
+                message: err.to_string(),
+                path,
+                extensions: ErrorExtensions { code },
+            }],
+        },
+    )
+}
+
+/// The HTTP status, `extensions.code`, and GraphQL error `path` for a given
+/// `QueryError`. Split out from [`response_for`] so the mapping can be unit
+/// tested without going through `tracing::Span::current()`.
+fn status_code_and_extensions(err: &QueryError) -> (StatusCode, &'static str, Option<Vec<String>>) {
+    // QueryError is defined in apollo_stargate_lib, a crate outside this
+    // checkout; treat it as non_exhaustive so a variant we don't recognize
+    // yet still produces a (generic, but spec-compliant) error response
+    // instead of failing to compile or panicking.
+    match err {
+        QueryError::ValidationError { .. } => {
+            (StatusCode::BAD_REQUEST, "GRAPHQL_VALIDATION_FAILED", None)
+        }
+        QueryError::PlanningError { .. } => (StatusCode::OK, "QUERY_PLANNING_FAILED", None),
+        QueryError::FetchError { path, .. } => {
+            (StatusCode::OK, "SUBGRAPH_FETCH_FAILED", Some(path.clone()))
+        }
+        QueryError::Timeout { .. } => (StatusCode::OK, "TIMEOUT", None),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_SERVER_ERROR", None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validation_error_is_a_bad_request() {
+        let err = QueryError::ValidationError {
+            message: String::from("missing field 'id'"),
+        };
+        let (status, code, path) = status_code_and_extensions(&err);
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(code, "GRAPHQL_VALIDATION_FAILED");
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn fetch_error_carries_its_path() {
+        let err = QueryError::FetchError {
+            message: String::from("subgraph unreachable"),
+            path: vec![String::from("me"), String::from("reviews")],
+        };
+        let (status, code, path) = status_code_and_extensions(&err);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(code, "SUBGRAPH_FETCH_FAILED");
+        assert_eq!(path, Some(vec![String::from("me"), String::from("reviews")]));
+    }
+
+    #[test]
+    fn timeout_reports_ok_with_timeout_code() {
+        let err = QueryError::Timeout {
+            message: String::from("deadline exceeded"),
+        };
+        let (status, code, _path) = status_code_and_extensions(&err);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(code, "TIMEOUT");
+    }
+}
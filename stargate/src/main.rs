@@ -1,54 +1,175 @@
 use actix_cors::Cors;
-use actix_web::{middleware, post, web, App, HttpResponse, HttpServer, Result};
+use actix_web::{middleware, post, web, App, HttpRequest, HttpResponse, HttpServer, Result};
+use apollo_reporting::{ApolloReporter, Trace};
 use apollo_stargate_lib::common::Opt;
 use apollo_stargate_lib::transports::http::{GraphQLRequest, RequestContext, ServerState};
 use apollo_stargate_lib::Stargate;
-use opentelemetry::api::{Key, Provider};
+use cli::{TraceExporter, TraceSampler, TracingOpt};
+use opentelemetry::api::{
+    BaggagePropagator, Extractor, Key, Provider, TextMapCompositePropagator, TraceContextPropagator,
+};
+use opentelemetry::exporter::trace::SpanExporter;
+use opentelemetry::global;
 use opentelemetry::sdk;
 use std::fs;
-use tracing::{debug, instrument};
+use std::time::{Instant, SystemTime};
+use structopt::StructOpt;
+use tracing::{debug, instrument, Span};
 use tracing_actix_web::TracingLogger;
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_log::LogTracer;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{EnvFilter, Registry};
 
+mod apollo_reporting;
+mod cli;
+mod error;
+
+/// Adapts an actix-web `HeaderMap` to the `opentelemetry` `Extractor` trait so
+/// incoming W3C trace context can be pulled out of the request headers.
+struct HeaderExtractor<'a>(&'a actix_web::http::HeaderMap);
+
+impl<'a> Extractor for HeaderExtractor<'a> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// Request-handler state: the stargate instance plus whatever this crate
+/// adds on top of it. Kept separate from `ServerState` (owned by
+/// `apollo_stargate_lib`) so the Apollo Studio reporter doesn't require
+/// changes to that crate.
+struct AppState {
+    server_state: ServerState<'static>,
+    apollo_reporter: Option<ApolloReporter>,
+}
+
 #[post("/")]
-#[instrument(skip(request, data))]
+#[instrument(
+    skip(http_request, request, data),
+    fields(
+        otel.status_code = tracing::field::Empty,
+        error.message = tracing::field::Empty,
+        apollo_private.ftv1_reported = tracing::field::Empty,
+    )
+)]
 async fn index(
+    http_request: HttpRequest,
     request: web::Json<GraphQLRequest>,
-    data: web::Data<ServerState<'static>>,
+    data: web::Data<AppState>,
 ) -> Result<HttpResponse> {
+    let parent_context = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(http_request.headers()))
+    });
+    Span::current().set_parent(parent_context);
+
+    let started_at = SystemTime::now();
+    let start_instant = Instant::now();
+
     let ql_request = request.into_inner();
     let context = RequestContext {
         graphql_request: ql_request,
     };
-    let result = match data.stargate.execute_query(&context).await {
-        Ok(result) => result,
-        Err(_) => todo!("handle error cases when executing query"),
-    };
-    Ok(HttpResponse::Ok().json(result))
+    match data.server_state.stargate.execute_query(&context).await {
+        Ok(result) => {
+            if let Some(reporter) = &data.apollo_reporter {
+                reporter.submit(Trace::new(started_at, start_instant));
+                Span::current().record("apollo_private.ftv1_reported", &true);
+            }
+            Ok(HttpResponse::Ok().json(result))
+        }
+        Err(err) => {
+            let (status, body) = error::response_for(err);
+            if let Some(reporter) = &data.apollo_reporter {
+                let mut trace = Trace::new(started_at, start_instant);
+                for error in &body.errors {
+                    if let Some(path) = &error.path {
+                        trace.record_error(path, error.message.clone());
+                    }
+                }
+                reporter.submit(trace);
+                Span::current().record("apollo_private.ftv1_reported", &true);
+            }
+            Ok(HttpResponse::build(status).json(body))
+        }
+    }
 }
 
 static mut MANIFEST: String = String::new();
 
-fn init_observability(structured_logging: bool) -> Result<(), Box<dyn std::error::Error>> {
+fn build_span_exporter(
+    trace_exporter: &TraceExporter,
+    trace_endpoint: &str,
+) -> Result<Box<dyn SpanExporter>, Box<dyn std::error::Error>> {
+    match trace_exporter {
+        TraceExporter::Jaeger => {
+            debug!("initializing jaeger trace exporter");
+            let exporter = opentelemetry_jaeger::Exporter::builder()
+                .with_collector_endpoint(trace_endpoint)
+                .with_process(opentelemetry_jaeger::Process {
+                    service_name: String::from("stargate"),
+                    tags: vec![Key::new("exporter").string("jaeger")],
+                })
+                .init()?;
+            Ok(Box::new(exporter))
+        }
+        TraceExporter::OtlpGrpc => {
+            debug!("initializing otlp/grpc trace exporter");
+            let exporter = opentelemetry_otlp::Exporter::builder()
+                .with_endpoint(trace_endpoint)
+                .with_protocol(opentelemetry_otlp::Protocol::Grpc)
+                .build_span_exporter()?;
+            Ok(Box::new(exporter))
+        }
+        TraceExporter::OtlpHttp => {
+            debug!("initializing otlp/http trace exporter");
+            let exporter = opentelemetry_otlp::Exporter::builder()
+                .with_endpoint(trace_endpoint)
+                .with_protocol(opentelemetry_otlp::Protocol::HttpJson)
+                .build_span_exporter()?;
+            Ok(Box::new(exporter))
+        }
+    }
+}
+
+fn build_sampler(trace_sampler: &TraceSampler, trace_sample_ratio: f64) -> sdk::Sampler {
+    let base = match trace_sampler {
+        TraceSampler::AlwaysOn => sdk::Sampler::AlwaysOn,
+        TraceSampler::AlwaysOff => sdk::Sampler::AlwaysOff,
+        TraceSampler::Ratio => sdk::Sampler::Probability(trace_sample_ratio),
+    };
+    // Honor the upstream sampling decision from an incoming `traceparent`
+    // when there is one, and only fall back to our own configured sampler
+    // for root spans.
+    sdk::Sampler::Parent(Box::new(base))
+}
+
+fn init_observability(opt: &Opt, tracing_opt: &TracingOpt) -> Result<(), Box<dyn std::error::Error>> {
     LogTracer::init().expect("Failed to set logger");
 
-    debug!("initializing jaeger trace exporter");
-    let jaeger_exporter = opentelemetry_jaeger::Exporter::builder()
-        .with_collector_endpoint("http://localhost:14268/api/traces")
-        .with_process(opentelemetry_jaeger::Process {
-            service_name: String::from("stargate"),
-            tags: vec![Key::new("exporter").string("jaeger")],
-        })
-        .init()?;
+    debug!("installing W3C trace-context propagator");
+    let propagator = TextMapCompositePropagator::new(vec![
+        Box::new(TraceContextPropagator::new()),
+        Box::new(BaggagePropagator::new()),
+    ]);
+    global::set_text_map_propagator(propagator);
+
+    let span_exporter =
+        build_span_exporter(&tracing_opt.trace_exporter, &tracing_opt.trace_endpoint)?;
 
     debug!("initializing trace provider");
     let provider = sdk::Provider::builder()
-        .with_simple_exporter(jaeger_exporter)
+        .with_simple_exporter(span_exporter)
         .with_config(sdk::Config {
-            default_sampler: Box::new(sdk::Sampler::AlwaysOn),
+            default_sampler: Box::new(build_sampler(
+                &tracing_opt.trace_sampler,
+                tracing_opt.trace_sample_ratio,
+            )),
             ..Default::default()
         })
         .build();
@@ -57,7 +178,7 @@ fn init_observability(structured_logging: bool) -> Result<(), Box<dyn std::error
         .with(tracing_opentelemetry::layer().with_tracer(provider.get_tracer("stargate")))
         .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")));
 
-    if structured_logging {
+    if opt.structured_logging {
         let subscriber = subscriber
             .with(JsonStorageLayer)
             .with(BunyanFormattingLayer::new(
@@ -80,14 +201,28 @@ fn init_observability(structured_logging: bool) -> Result<(), Box<dyn std::error
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     let opt = Opt::default();
-    init_observability(opt.structured_logging).expect("failed to initialize tracer.");
+    let tracing_opt = TracingOpt::from_args();
+    init_observability(&opt, &tracing_opt).expect("failed to initialize tracer.");
 
     debug!("Initializing stargate instance");
     let stargate = unsafe {
         MANIFEST = fs::read_to_string(&opt.manifest)?;
         Stargate::new(&MANIFEST)
     };
-    let stargate = web::Data::new(ServerState { stargate });
+    let apollo_reporter = match (&tracing_opt.apollo_key, &tracing_opt.apollo_graph_ref) {
+        (Some(apollo_key), Some(apollo_graph_ref)) => {
+            debug!("enabling apollo studio FTV1 trace reporting");
+            Some(ApolloReporter::new(
+                apollo_key.clone(),
+                apollo_graph_ref.clone(),
+            ))
+        }
+        _ => None,
+    };
+    let app_state = web::Data::new(AppState {
+        server_state: ServerState { stargate },
+        apollo_reporter,
+    });
 
     HttpServer::new(move || {
         let cors = Cors::new()
@@ -97,7 +232,7 @@ async fn main() -> std::io::Result<()> {
             .finish();
 
         App::new()
-            .app_data(stargate.clone())
+            .app_data(app_state.clone())
             .wrap(middleware::Logger::default())
             .wrap(TracingLogger)
             .wrap(middleware::Compress::default())
@@ -108,3 +243,27 @@ async fn main() -> std::io::Result<()> {
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratio_sampler_wraps_a_probability_sampler() {
+        match build_sampler(&TraceSampler::Ratio, 0.25) {
+            sdk::Sampler::Parent(base) => match *base {
+                sdk::Sampler::Probability(ratio) => assert_eq!(ratio, 0.25),
+                other => panic!("expected a probability sampler, got {:?}", other),
+            },
+            other => panic!("expected a parent-based sampler, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn always_off_sampler_wraps_always_off() {
+        match build_sampler(&TraceSampler::AlwaysOff, 1.0) {
+            sdk::Sampler::Parent(base) => assert!(matches!(*base, sdk::Sampler::AlwaysOff)),
+            other => panic!("expected a parent-based sampler, got {:?}", other),
+        }
+    }
+}